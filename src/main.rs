@@ -1,15 +1,221 @@
 use quicksilver::{
     combinators::result,
-    geom::{Rectangle, Shape, Vector},
+    geom::{Rectangle, Shape, Transform, Vector},
     graphics::{
         Background::{Blended, Img},
         Color, Font, FontStyle, Image,
     },
+    input::{ButtonState, Key},
     lifecycle::{run, Asset, Settings, State, Window},
     Future, Result,
 };
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+
+mod gamma;
+mod tileset;
+
+use gamma::GammaCorrection;
+use tileset::Tileset;
+
+/// How many rasterized glyphs `GlyphCache` is allowed to hold onto at once
+/// before it starts evicting the least-recently-used ones.
+const GLYPH_CACHE_CAPACITY: usize = 256;
+
+/// Horizontal shear applied to synthesize italics, i.e. `x' = x + y * tan(θ)`
+/// with `θ ≈ 14°`. The Square tilemap font only ships a regular weight, so
+/// slant is faked at draw time rather than rasterized from a real italic.
+const SYNTHETIC_ITALIC_SHEAR: f32 = 0.25;
+
+/// Pixel offset of the second pass when synthesizing bold by drawing a
+/// glyph twice, the way Alacritty cheaply emboldens a regular-weight font.
+const SYNTHETIC_BOLD_OFFSET: f32 = 1.0;
+
+/// Synthetic styling applied to a glyph at draw time, for fonts (like the
+/// Square tilemap font) that only ship a regular weight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum GlyphStyle {
+    Regular,
+    Bold,
+    Italic,
+}
+
+/// Identifies one rasterized glyph: which character, at which pixel size,
+/// drawn with which font and synthetic style. Mirrors the FontKey/GlyphKey
+/// split Alacritty uses to key its glyph atlas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    glyph: char,
+    size: u32,
+    font_id: u8,
+    style: GlyphStyle,
+}
+
+/// Whether `image` has any actual ink, rather than just being a nonzero
+/// size. `Font::render` returns a nonzero-width `.notdef` box for a
+/// codepoint the font lacks, so checking width alone would accept every
+/// font in the chain; scanning the alpha channel for real coverage is what
+/// actually tells a covered glyph apart from a blank placeholder.
+fn has_ink(image: &Image) -> bool {
+    image.raw_pixels().chunks(4).any(|pixel| pixel[3] > 0)
+}
+
+/// An ordered chain of fonts queried one at a time when rendering a glyph,
+/// so characters outside the primary font (Unicode symbols, emoji) still
+/// render via a fallback font instead of vanishing. This is the fallback
+/// model skribo and Neovide use: a primary font plus one or more
+/// emoji/symbol fallbacks, tried in order per character.
+struct FontCollection {
+    fonts: Vec<Font>,
+}
+
+impl FontCollection {
+    fn new(fonts: Vec<Font>) -> Self {
+        assert!(!fonts.is_empty(), "FontCollection must not be empty.");
+        Self { fonts }
+    }
+
+    fn len(&self) -> usize {
+        self.fonts.len()
+    }
+
+    /// Renders `c` with the first font in the chain that actually has a
+    /// non-empty raster for it, returning which font produced the image
+    /// alongside the image itself. Falls back to the last font's `.notdef`
+    /// box if none of them cover the character.
+    fn render_glyph(&self, c: char, style: &FontStyle) -> (u8, Image) {
+        for (index, font) in self.fonts.iter().enumerate() {
+            if let Ok(image) = font.render(&c.to_string(), style) {
+                if has_ink(&image) {
+                    return (index as u8, image);
+                }
+            }
+        }
+
+        let notdef_index = self.fonts.len() - 1;
+        let notdef = self.fonts[notdef_index]
+            .render(&c.to_string(), style)
+            .expect("Could not rasterize .notdef glyph.");
+        (notdef_index as u8, notdef)
+    }
+}
+
+/// Where a `GlyphCache` gets its raw glyph images from: a TTF font chain,
+/// rendered to a bitmap per glyph, or a pre-baked CP437 bitmap tileset
+/// sliced up front. Either way the cache (and the draw loops) only ever
+/// look a glyph up by `char`.
+enum GlyphSource {
+    Fonts(FontCollection),
+    Tileset(Tileset),
+}
+
+impl GlyphSource {
+    /// Number of distinct `font_id`s this source can produce, for
+    /// `GlyphCache::resident_key` to probe. A tileset only ever has one.
+    fn font_count(&self) -> usize {
+        match self {
+            GlyphSource::Fonts(fonts) => fonts.len(),
+            GlyphSource::Tileset(_) => 1,
+        }
+    }
+
+    fn render_glyph(&self, c: char, size: u32) -> (u8, Image) {
+        match self {
+            GlyphSource::Fonts(fonts) => {
+                let style = FontStyle::new(size as f32, Color::WHITE);
+                fonts.render_glyph(c, &style)
+            }
+            GlyphSource::Tileset(tileset) => {
+                let image = tileset
+                    .image_for(c)
+                    .unwrap_or_else(|| tileset.image_for(' ').expect("tileset has no blank tile"))
+                    .clone();
+                (0, image)
+            }
+        }
+    }
+}
+
+/// A bounded, on-demand glyph atlas: renders a glyph the first time it's
+/// requested and reuses the result afterwards, evicting the
+/// least-recently-used entry once `capacity` is exceeded. This replaces
+/// building a fixed tilemap up front, so any glyph (not just the ones in a
+/// hard-coded source string) can be rendered at any size.
+struct GlyphCache {
+    source: GlyphSource,
+    capacity: usize,
+    gamma: Option<GammaCorrection>,
+    images: HashMap<GlyphKey, Image>,
+    recency: VecDeque<GlyphKey>,
+}
+
+impl GlyphCache {
+    fn new(source: GlyphSource, capacity: usize, gamma: Option<GammaCorrection>) -> Self {
+        Self {
+            source,
+            capacity,
+            gamma,
+            images: HashMap::new(),
+            recency: VecDeque::new(),
+        }
+    }
+
+    /// Moves `key` to the most-recently-used position.
+    fn touch(&mut self, key: GlyphKey) {
+        if let Some(pos) = self.recency.iter().position(|resident| *resident == key) {
+            self.recency.remove(pos);
+        }
+        self.recency.push_back(key);
+    }
+
+    /// Returns the key this glyph is (or would be) cached under, by
+    /// checking every font in the chain rather than rasterizing again.
+    fn resident_key(&self, glyph: char, size: u32, style: GlyphStyle) -> Option<GlyphKey> {
+        (0..self.source.font_count() as u8)
+            .map(|font_id| GlyphKey {
+                glyph,
+                size,
+                font_id,
+                style,
+            })
+            .find(|key| self.images.contains_key(key))
+    }
+
+    /// Returns the cached image for `glyph` at `size`, resolving it through
+    /// the glyph source and inserting it on a miss. Trims the oldest entry
+    /// once the cache grows past capacity. The raster itself is identical
+    /// across `GlyphStyle`s (neither glyph source ships more than one
+    /// weight); bold and italic are synthesized by the caller at draw time,
+    /// but `style` still lives in the key so a future per-style raster
+    /// wouldn't evict the regular glyph it was derived from.
+    fn lookup(&mut self, glyph: char, size: u32, style: GlyphStyle) -> &Image {
+        let key = match self.resident_key(glyph, size, style) {
+            Some(key) => key,
+            None => {
+                let (font_id, image) = self.source.render_glyph(glyph, size);
+                let image = match &self.gamma {
+                    Some(gamma) => gamma.apply(&image),
+                    None => image,
+                };
+                let key = GlyphKey {
+                    glyph,
+                    size,
+                    font_id,
+                    style,
+                };
+                self.images.insert(key, image);
+                if self.images.len() > self.capacity {
+                    if let Some(lru) = self.recency.pop_front() {
+                        self.images.remove(&lru);
+                    }
+                }
+                key
+            }
+        };
+        self.touch(key);
+        self.images.get(&key).expect("glyph was just inserted")
+    }
+}
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 struct Entity {
@@ -17,6 +223,7 @@ struct Entity {
     y: i32,
     glyph: char,
     color: Color,
+    style: GlyphStyle,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -27,10 +234,12 @@ struct Tile {
     color: Color,
 }
 
+// Stored in row-major order (`y * width + x`) so `Game::tile_at` can index
+// straight into the flat `Vec` instead of scanning for a matching tile.
 fn generate_map(width: usize, height: usize) -> Vec<Tile> {
     let mut map = Vec::with_capacity(width * height);
-    for x in 0..width {
-        for y in 0..height {
+    for y in 0..height {
+        for x in 0..width {
             let mut tile = Tile {
                 x: x as i32,
                 y: y as i32,
@@ -49,31 +258,128 @@ fn generate_map(width: usize, height: usize) -> Vec<Tile> {
 
 fn generate_entities() -> Vec<Entity> {
     vec![
+        // A tougher goblin, drawn bold to stand out as more dangerous.
         Entity {
             x: 9,
             y: 6,
             glyph: 'g',
             color: Color::RED,
+            style: GlyphStyle::Bold,
         },
         Entity {
             x: 2,
             y: 4,
             glyph: 'g',
             color: Color::RED,
+            style: GlyphStyle::Regular,
         },
     ]
 }
 
+/// Draws `image` at `pos`, synthesizing `style` since the tilemap font only
+/// ships a regular weight. Bold is a second pass offset by
+/// `SYNTHETIC_BOLD_OFFSET` pixels (a cheap emboldening dilation); italic is
+/// a horizontal shear applied via a `Transform`.
+fn draw_glyph(window: &mut Window, pos: Vector, image: &Image, color: Color, style: GlyphStyle) {
+    let area = Rectangle::new(pos, image.area().size());
+    match style {
+        GlyphStyle::Regular => {
+            window.draw(&area, Blended(image, color));
+        }
+        GlyphStyle::Bold => {
+            window.draw(&area, Blended(image, color));
+            let offset_area = Rectangle::new(
+                pos + Vector::new(SYNTHETIC_BOLD_OFFSET, 0.0),
+                image.area().size(),
+            );
+            window.draw(&offset_area, Blended(image, color));
+        }
+        GlyphStyle::Italic => {
+            let shear = Transform::from_array([
+                [1.0, SYNTHETIC_ITALIC_SHEAR, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 0.0, 1.0],
+            ]);
+            window.draw_ex(&area, Blended(image, color), shear, 0);
+        }
+    }
+}
+
 struct Game {
     title: Asset<Image>,
     mononoki_font_info: Asset<Image>,
     square_font_info: Asset<Image>,
-    tilemap: Asset<HashMap<char, Image>>,
+    glyphs: Asset<GlyphCache>,
     map: Vec<Tile>,
+    width: usize,
+    height: usize,
     entities: Vec<Entity>,
     player_id: usize,
 }
 
+/// Set to `true` to slice glyphs out of a CP437 bitmap tilesheet instead of
+/// rasterizing them from the TTF fonts below.
+const USE_CP437_TILESET: bool = false;
+
+/// Size, in pixels, of one cell in the `cp437_8x8.png` tilesheet.
+const CP437_CELL_SIZE: (i32, i32) = (8, 8);
+
+impl Game {
+    /// Builds the glyph cache from whichever source is configured:
+    /// `USE_CP437_TILESET` picks a pre-baked CP437 bitmap tilesheet,
+    /// otherwise glyphs are rasterized on demand from the TTF font chain.
+    /// `gamma` is applied to each glyph as it's rasterized, before it
+    /// enters the cache. `GammaCorrection::apply` assumes a tightly-packed
+    /// RGBA buffer, which doesn't hold for the tileset's `subimage` slices
+    /// of the shared sheet, so gamma is forced off when the tileset is in
+    /// use rather than risk corrupting/misaligning its output.
+    fn load_glyphs(
+        font_square: &'static str,
+        font_mononoki: &'static str,
+        gamma: Option<GammaCorrection>,
+    ) -> Asset<GlyphCache> {
+        if USE_CP437_TILESET {
+            Asset::new(Image::load("cp437_8x8.png").and_then(move |sheet| {
+                let tileset = Tileset::from_image(&sheet, CP437_CELL_SIZE);
+                result(Ok(GlyphCache::new(
+                    GlyphSource::Tileset(tileset),
+                    GLYPH_CACHE_CAPACITY,
+                    None,
+                )))
+            }))
+        } else {
+            Asset::new(
+                Font::load(font_square)
+                    .join(Font::load(font_mononoki))
+                    .and_then(move |(square, mononoki)| {
+                        let fonts = FontCollection::new(vec![square, mononoki]);
+                        result(Ok(GlyphCache::new(
+                            GlyphSource::Fonts(fonts),
+                            GLYPH_CACHE_CAPACITY,
+                            gamma,
+                        )))
+                    }),
+            )
+        }
+    }
+
+    /// Looks up the tile at `(x, y)`, or `None` if that's outside the map.
+    fn tile_at(&self, x: i32, y: i32) -> Option<&Tile> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        self.map.get(y as usize * self.width + x as usize)
+    }
+
+    /// Whether some entity other than `player_id` already occupies `(x, y)`.
+    fn occupied_by_other(&self, x: i32, y: i32, player_id: usize) -> bool {
+        self.entities
+            .iter()
+            .enumerate()
+            .any(|(id, entity)| id != player_id && entity.x == x && entity.y == y)
+    }
+}
+
 impl State for Game {
     fn new() -> Result<Self> {
         // The Mononoki font: https://madmalik.github.io/mononoki/
@@ -101,23 +407,13 @@ impl State for Game {
             )
         }));
 
-        let tilemap_source = "#@g.";
-        let (width, height) = (24, 24);
-        let tilemap = Asset::new(Font::load(font_square).and_then(move |text| {
-            let tiles = text
-                .render(tilemap_source, &FontStyle::new(height as f32, Color::WHITE))
-                .expect("Could not render the font tilemap.");
-            let mut tilemap = HashMap::new();
-            for (index, glyph) in tilemap_source.chars().enumerate() {
-                let pos = (index as i32 * width, 0);
-                let size = (width, height);
-                let tile = tiles.subimage(Rectangle::new(pos, size));
-                tilemap.insert(glyph, tile);
-            }
-            result(Ok(tilemap))
-        }));
+        // Disabled by default: `None` reproduces the old straight linear-alpha
+        // output exactly. Flip to `Some(GammaCorrection::new(2.2, 1.1))` for
+        // perceptually-corrected glyph coverage.
+        let glyphs = Self::load_glyphs(font_square, font_mononoki, None);
 
-        let map = generate_map(20, 15);
+        let (width, height) = (20, 15);
+        let map = generate_map(width, height);
         let mut entities = generate_entities();
         let player_id = entities.len();
         entities.push(Entity {
@@ -125,19 +421,54 @@ impl State for Game {
             y: 3,
             glyph: '@',
             color: Color::BLUE,
+            style: GlyphStyle::Regular,
         });
 
         Ok(Self {
             title,
             mononoki_font_info,
             square_font_info,
-            tilemap,
+            glyphs,
             map,
+            width,
+            height,
             entities,
             player_id,
         })
     }
 
+    fn update(&mut self, window: &mut Window) -> Result<()> {
+        let delta = if window.keyboard()[Key::Left] == ButtonState::Pressed {
+            Some((-1, 0))
+        } else if window.keyboard()[Key::Right] == ButtonState::Pressed {
+            Some((1, 0))
+        } else if window.keyboard()[Key::Up] == ButtonState::Pressed {
+            Some((0, -1))
+        } else if window.keyboard()[Key::Down] == ButtonState::Pressed {
+            Some((0, 1))
+        } else {
+            None
+        };
+
+        if let Some((dx, dy)) = delta {
+            let player = self.entities[self.player_id];
+            let (target_x, target_y) = (player.x + dx, player.y + dy);
+
+            let walkable = self
+                .tile_at(target_x, target_y)
+                .map_or(false, |tile| tile.glyph == '.');
+            let occupied = self.occupied_by_other(target_x, target_y, self.player_id);
+
+            if walkable && !occupied {
+                let player = &mut self.entities[self.player_id];
+                player.x = target_x;
+                player.y = target_y;
+            }
+        }
+
+        Ok(())
+    }
+
     fn draw(&mut self, window: &mut Window) -> Result<()> {
         window.clear(Color::WHITE)?;
 
@@ -173,32 +504,24 @@ impl State for Game {
 
         // NOTE: Need to do partial borrows here to prevent borrowing
         // the whole self as mutable.
-        let (tilemap, map) = (&mut self.tilemap, &self.map);
-        tilemap.execute(|tilemap| {
+        let (glyphs, map) = (&mut self.glyphs, &self.map);
+        glyphs.execute(|glyphs| {
             let offset = Vector::new(50, 150);
             for tile in map.iter() {
-                if let Some(image) = tilemap.get(&tile.glyph) {
-                    let pos = (tile.x * 24, tile.y * 24);
-                    window.draw(
-                        &Rectangle::new(offset.translate(pos), image.area().size()),
-                        Blended(&image, tile.color),
-                    );
-                }
+                let image = glyphs.lookup(tile.glyph, 24, GlyphStyle::Regular);
+                let pos = (tile.x * 24, tile.y * 24);
+                draw_glyph(window, offset.translate(pos), image, tile.color, GlyphStyle::Regular);
             }
             Ok(())
         })?;
 
-        let (tilemap, entities) = (&mut self.tilemap, &self.entities);
-        tilemap.execute(|tilemap| {
+        let (glyphs, entities) = (&mut self.glyphs, &self.entities);
+        glyphs.execute(|glyphs| {
             let offset = Vector::new(50, 150);
             for entity in entities.iter() {
-                if let Some(image) = tilemap.get(&entity.glyph) {
-                    let pos = (entity.x * 24, entity.y * 24);
-                    window.draw(
-                        &Rectangle::new(offset.translate(pos), image.area().size()),
-                        Blended(&image, entity.color),
-                    );
-                }
+                let image = glyphs.lookup(entity.glyph, 24, entity.style);
+                let pos = (entity.x * 24, entity.y * 24);
+                draw_glyph(window, offset.translate(pos), image, entity.color, entity.style);
             }
             Ok(())
         })?;
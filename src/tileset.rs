@@ -0,0 +1,66 @@
+//! A classic Code Page 437 bitmap tilesheet: a 16x16 grid of fixed-size
+//! cells, sliced into 256 subimages and looked up by `char` the same way
+//! `GlyphCache` looks up a rasterized TTF glyph. This lets users drop in
+//! off-the-shelf roguelike tilesets (walls, floors, doodads) that only
+//! exist as bitmaps, not font glyphs.
+
+use quicksilver::{geom::Rectangle, graphics::Image};
+
+/// Number of cells per row/column in a CP437 tilesheet.
+const CP437_GRID: usize = 16;
+
+/// Maps each CP437 code point (0-255) to the Unicode character it
+/// represents, so callers can look a tile up by `char` instead of by raw
+/// byte. Entries with no sensible Unicode counterpart fall back to a
+/// space; `Tileset::image_for` treats that as "not covered".
+const CP437_TABLE: [char; 256] = [
+    ' ', '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼', '►', '◄', '↕',
+    '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼', ' ', '!', '"', '#', '$', '%',
+    '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', '0', '1', '2', '3', '4', '5', '6', '7', '8',
+    '9', ':', ';', '<', '=', '>', '?', '@', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K',
+    'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '[', '\\', ']', '^',
+    '_', '`', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q',
+    'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '{', '|', '}', '~', '⌂', 'Ç', 'ü', 'é', 'â', 'ä',
+    'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', 'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù',
+    'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', 'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬',
+    '½', '¼', '¡', '«', '»', '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜',
+    '╛', '┐', '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', '╨',
+    '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', 'α', 'ß', 'Γ', 'π',
+    'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', '≡', '±', '≥', '≤', '⌠', '⌡', '÷',
+    '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', ' ',
+];
+
+/// A CP437 bitmap tilesheet, sliced into per-glyph subimages.
+pub struct Tileset {
+    cells: Vec<Image>,
+    index_for: std::collections::HashMap<char, usize>,
+}
+
+impl Tileset {
+    /// Slices `sheet` into a `CP437_GRID` x `CP437_GRID` grid of
+    /// `cell_size`-sized subimages, indexed by CP437 byte.
+    pub fn from_image(sheet: &Image, cell_size: (i32, i32)) -> Self {
+        let (cell_width, cell_height) = cell_size;
+        let mut cells = Vec::with_capacity(256);
+        let mut index_for = std::collections::HashMap::new();
+
+        for index in 0..256 {
+            let column = (index % CP437_GRID) as i32;
+            let row = (index / CP437_GRID) as i32;
+            let pos = (column * cell_width, row * cell_height);
+            cells.push(sheet.subimage(Rectangle::new(pos, cell_size)));
+
+            let glyph = CP437_TABLE[index];
+            if glyph != ' ' || index == 0x20 {
+                index_for.entry(glyph).or_insert(index);
+            }
+        }
+
+        Self { cells, index_for }
+    }
+
+    /// Returns the tile image for `glyph`, if this tileset covers it.
+    pub fn image_for(&self, glyph: char) -> Option<&Image> {
+        self.index_for.get(&glyph).map(|&index| &self.cells[index])
+    }
+}